@@ -2,10 +2,18 @@ use uucore::{format_usage, help_about, help_usage};
 use clap::{crate_version, Arg, ArgAction, Command};
 
 mod platform;
+#[cfg(all(unix, not(target_os = "openbsd")))]
+mod wtmp;
 
 mod options {
     pub const SYSTEM: &str = "system";
     pub const FILE: &str = "file";
+    pub const ARGS: &str = "arg";
+    pub const TIME_FORMAT: &str = "time-format";
+    pub const BYTEORDER: &str = "byteorder";
+    pub const FORMAT: &str = "format";
+    pub const PRESENT: &str = "present";
+    pub const LIMIT: &str = "limit";
 }
 
 const ABOUT: &str = help_about!("last.md");
@@ -37,4 +45,55 @@ pub fn uu_app() -> Command {
                 .required(false)
                 .help("display system shutdown entries and run level changes")
         )
+        .arg(
+            Arg::new(options::ARGS)
+                .action(ArgAction::Append)
+                .num_args(1..)
+                .help("username or tty to filter the output by")
+        )
+        .arg(
+            Arg::new(options::TIME_FORMAT)
+                .long(options::TIME_FORMAT)
+                .action(ArgAction::Set)
+                .value_parser(["notime", "short", "full", "iso"])
+                .default_value("short")
+                .help("show timestamps in the specified format: notime|short|full|iso")
+                .required(false)
+        )
+        .arg(
+            Arg::new(options::BYTEORDER)
+                .long(options::BYTEORDER)
+                .action(ArgAction::Set)
+                .value_parser(["native", "little", "big"])
+                .default_value("native")
+                .help("byte order of the records in the wtmp/utmpx file")
+                .required(false)
+        )
+        .arg(
+            Arg::new(options::FORMAT)
+                .long(options::FORMAT)
+                .action(ArgAction::Set)
+                .value_parser(["utmpx", "utmp"])
+                .default_value("utmpx")
+                .help("record layout of the wtmp/utmpx file")
+                .required(false)
+        )
+        .arg(
+            Arg::new(options::PRESENT)
+                .long(options::PRESENT)
+                .action(ArgAction::Set)
+                .value_name("TIME")
+                .help("show only sessions that were active at the given date/time, e.g. \"2024-01-31 14:30\" or \"14:30\"")
+                .required(false)
+        )
+        .arg(
+            Arg::new(options::LIMIT)
+                .short('n')
+                .long(options::LIMIT)
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .value_name("NUM")
+                .help("show only the NUM most recent lines")
+                .required(false)
+        )
 }
\ No newline at end of file
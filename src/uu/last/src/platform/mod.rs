@@ -3,9 +3,9 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-#[cfg(unix)]
+#[cfg(all(unix, not(target_os = "openbsd")))]
 mod unix;
-#[cfg(unix)]
+#[cfg(all(unix, not(target_os = "openbsd")))]
 pub use self::unix::*;
 
 #[cfg(target_os = "openbsd")]
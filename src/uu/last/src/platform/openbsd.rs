@@ -0,0 +1,282 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// Specific implementation for OpenBSD: OpenBSD never adopted the utmpx(5)
+// interface, so instead of `uucore::utmpx::Utmpx` this backend reads the
+// classic (pre-utmpx) `utmp`/`wtmp` record layout directly via the
+// `utmp-classic` crate. Unlike `uucore::utmpx::Utmpx`, `utmp_classic::UtmpEntry`
+// is an enum over the classic `ut_type` values, so each record is matched by
+// variant rather than read as a flat C struct.
+
+use crate::options;
+use crate::uu_app;
+
+use uucore::error::{UResult, USimpleError};
+
+use utmp_classic::{parse_from_path, UtmpEntry};
+
+use time::{OffsetDateTime, format_description::FormatItem};
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+fn get_long_usage() -> String {
+    "For more details see last(1).".to_string()
+}
+
+const WTMP_PATH: &str = "/var/log/wtmp";
+
+pub fn uumain(args: impl uucore::Args) -> UResult<()> {
+    let matches = uu_app()
+        .after_help(get_long_usage())
+        .try_get_matches_from(args)?;
+
+    // `--system` only affects shutdown/runlevel entries, which this backend
+    // never prints, so the flag has no effect here.
+    let _system = matches.get_flag(options::SYSTEM);
+
+    let file: String = if let Some(files) = matches.get_one::<String>(options::FILE) {
+        files.to_string()
+    } else {
+        WTMP_PATH.to_string()
+    };
+
+    let users: Option<Vec<String>> = matches
+        .get_many::<String>(options::ARGS)
+        .map(|vals| vals.map(|val| val.to_string()).collect());
+
+    let limit = matches.get_one::<usize>(options::LIMIT).copied();
+
+    let mut last = Last {
+        last_reboot_time: None,
+        last_dead: vec![],
+        file,
+        users,
+        limit,
+    };
+
+    last.exec()
+}
+
+const REBOOT_STR: &str = "reboot";
+
+/// A session login, derived from a `UtmpEntry::UserProcess` record.
+struct UserSession {
+    line: String,
+    user: String,
+    host: String,
+    time: i32,
+}
+
+/// A session logout, derived from a `UtmpEntry::DeadProcess` record.
+struct DeadSession {
+    line: String,
+    time: i32,
+}
+
+struct Last {
+    last_reboot_time: Option<i32>,
+    last_dead: Vec<DeadSession>,
+    file: String,
+    users: Option<Vec<String>>,
+    limit: Option<usize>,
+}
+
+#[inline]
+fn calculate_time_delta(curr_time: i32, last_time: i32) -> time::Duration {
+    time::Duration::seconds((last_time - curr_time) as i64)
+}
+
+#[inline]
+fn duration_string(duration: time::Duration) -> String {
+    let mut seconds = duration.whole_seconds();
+
+    let days = seconds / 86400;
+    seconds -= days * 86400;
+    let hours = seconds / 3600;
+    seconds -= hours * 3600;
+    let minutes = seconds / 60;
+
+    if days > 0 {
+        format!("({}+{:0>2}:{:0>2})", days, hours, minutes)
+    } else {
+        format!("({:0>2}:{:0>2})", hours, minutes)
+    }
+}
+
+impl Last {
+    #[allow(clippy::cognitive_complexity)]
+    fn exec(&mut self) -> UResult<()> {
+        let entries = parse_from_path(&self.file)
+            .map_err(|e| USimpleError::new(1, format!("{}: {e}", self.file)))?;
+
+        // wtmp records are appended oldest-first; push them onto a stack so
+        // they can be popped newest-first, matching the unix backend.
+        let mut ut_stack: Vec<UtmpEntry> = entries;
+
+        let mut printed: usize = 0;
+        if self.limit == Some(0) {
+            return Ok(());
+        }
+
+        while let Some(ut) = ut_stack.pop() {
+            let did_print = match ut {
+                UtmpEntry::UserProcess { pid: _, line, user, host, time } => {
+                    let session = UserSession { line, user, host, time };
+                    let mut dead_proc: Option<DeadSession> = None;
+                    if let Some(pos) = self
+                        .last_dead
+                        .iter()
+                        .position(|dead| dead.line == session.line)
+                    {
+                        dead_proc = Some(self.last_dead.swap_remove(pos));
+                    }
+                    self.print_user(&session, dead_proc.as_ref())
+                }
+                UtmpEntry::DeadProcess { pid: _, line, time } => {
+                    // logout record: remember it so the matching login
+                    // record can compute its session length
+                    self.last_dead.push(DeadSession { line, time });
+                    false
+                }
+                UtmpEntry::BootTime { time } => {
+                    let did_print = self.print_reboot(time);
+                    self.last_reboot_time = Some(time);
+                    did_print
+                }
+                // Run-level changes, old/new time markers, init/login
+                // process bookkeeping and accounting records have no
+                // equivalent in `last`'s output.
+                _ => false,
+            };
+
+            if did_print {
+                printed += 1;
+                if let Some(limit) = self.limit {
+                    if printed >= limit {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `--` positional arguments restrict output to this session's
+    /// user or tty. Reboot records have neither, so this only applies to
+    /// user sessions.
+    #[inline]
+    fn matches_filter(&self, user: &str, line: &str) -> bool {
+        match &self.users {
+            None => true,
+            Some(users) => users.iter().any(|val| {
+                let val = val.trim();
+                val == user.trim() || val == line.trim()
+            }),
+        }
+    }
+
+    #[inline]
+    fn time_string(&self, time: i32) -> String {
+        let description = "[month repr:short] [day padding:space] [hour]:[minute]";
+        let time_format: Vec<FormatItem> = time::format_description::parse(description).unwrap();
+        OffsetDateTime::from_unix_timestamp(time as i64)
+            .unwrap()
+            .format(&time_format)
+            .unwrap() // LC_ALL=C
+    }
+
+    #[inline]
+    fn end_time_string(&self, user_process_str: Option<&str>, end_time: i32) -> String {
+        match user_process_str {
+            Some(val) => val.to_string(),
+            _ => {
+                let description = "[hour]:[minute]";
+                let time_format: Vec<FormatItem> =
+                    time::format_description::parse(description).unwrap();
+                OffsetDateTime::from_unix_timestamp(end_time as i64)
+                    .unwrap()
+                    .format(&time_format)
+                    .unwrap() // LC_ALL=C
+            }
+        }
+    }
+
+    #[inline]
+    fn end_state_string(&self, session: &UserSession, dead: Option<&DeadSession>) -> (String, String) {
+        let mut proc_status: Option<&str> = None;
+
+        if let Some(dead) = dead {
+            let delta = duration_string(calculate_time_delta(session.time, dead.time));
+            return (self.end_time_string(proc_status, dead.time), delta);
+        }
+
+        match self.last_reboot_time {
+            None => (" - still logged in".to_string(), "".to_string()),
+            Some(reboot) => {
+                let delta = duration_string(calculate_time_delta(session.time, reboot));
+                proc_status = Some("crash");
+                (self.end_time_string(proc_status, reboot), delta)
+            }
+        }
+    }
+
+    #[inline]
+    fn print_reboot(&self, time: i32) -> bool {
+        self.print_line(REBOOT_STR, "system boot", &self.time_string(time), "", "", "");
+        true
+    }
+
+    #[inline]
+    fn print_user(&self, session: &UserSession, dead: Option<&DeadSession>) -> bool {
+        if !self.matches_filter(&session.user, &session.line) {
+            return false;
+        }
+
+        let mut p = PathBuf::from("/dev");
+        p.push(&session.line);
+
+        let (end_date, delta) = self.end_state_string(session, dead);
+
+        self.print_line(
+            &session.user,
+            &session.line,
+            self.time_string(session.time).as_str(),
+            &session.host,
+            &end_date,
+            &delta,
+        );
+
+        true
+    }
+
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    fn print_line(
+        &self,
+        user: &str,
+        line: &str,
+        time: &str,
+        host: &str,
+        end_time: &str,
+        delta: &str,
+    ) {
+        let mut buf = String::with_capacity(64);
+        let host_to_print = host.get(0..16).unwrap_or(host);
+
+        write!(buf, "{user:<8}").unwrap();
+        write!(buf, " {line:<12}").unwrap();
+        write!(buf, " {host_to_print:<16}").unwrap();
+
+        let time_size = 3 + 2 + 2 + 1 + 2;
+
+        write!(buf, " {time:<time_size$}").unwrap();
+        write!(buf, " - {end_time:<8}").unwrap();
+
+        write!(buf, " {delta:^6}").unwrap();
+        println!("{}", buf.trim_end());
+    }
+}
@@ -3,19 +3,20 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-// Specific implementation for OpenBSD: tool unsupported (utmpx not supported)
+// Specific implementation for Unix-like platforms that provide utmpx(5)
 
 use crate::uu_app;
 use crate::options;
+use crate::wtmp::{self, RecordByteOrder, RecordFormat};
 
-use uucore::error::UResult;
+use uucore::error::{UResult, USimpleError};
 
 use uucore::utmpx::time::OffsetDateTime;
 use uucore::utmpx::{time, Utmpx};
 
 use std::fmt::Write;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn get_long_usage() -> String {
     format!(
@@ -32,16 +33,37 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
 
     let system = matches.get_flag(options::SYSTEM);
 
-    let time_format = "short".to_string();  // TODO implement time formatting later;
+    let time_format = matches
+        .get_one::<String>(options::TIME_FORMAT)
+        .map(|val| val.to_string())
+        .unwrap_or_else(|| "short".to_string());
 
-    
     let file: String = if let Some(files) = matches.get_one::<String>(options::FILE) {
         files.to_string()
     } else {
         WTMP_PATH.to_string()
     };
 
-    let users: Option<Vec<String>> = None; // TODO implement user searching
+    let users: Option<Vec<String>> = matches
+        .get_many::<String>(options::ARGS)
+        .map(|vals| vals.map(|val| val.to_string()).collect());
+
+    let byteorder = RecordByteOrder::from_str_name(
+        matches.get_one::<String>(options::BYTEORDER).map(String::as_str).unwrap_or("native"),
+    )
+    .unwrap_or(RecordByteOrder::Native);
+
+    let format = RecordFormat::from_str_name(
+        matches.get_one::<String>(options::FORMAT).map(String::as_str).unwrap_or("utmpx"),
+    )
+    .unwrap_or(RecordFormat::Utmpx);
+
+    let present = matches
+        .get_one::<String>(options::PRESENT)
+        .map(|val| parse_present(val))
+        .transpose()?;
+
+    let limit = matches.get_one::<usize>(options::LIMIT).copied();
 
     let mut last = Last {
         last_reboot_ut: None,
@@ -50,24 +72,60 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         system,
         file: file.to_string(),
         users,
-        time_format
+        time_format,
+        offset: time::UtcOffset::UTC,
+        byteorder,
+        format,
+        present,
+        present_instant: None,
+        limit,
     };
 
     last.exec()
 }
 
+/// Parse a `--present` argument into a naive date-time: either a full
+/// `YYYY-MM-DD HH:MM` date-time, or a bare `HH:MM` anchored to today. It is
+/// intentionally left un-anchored to any offset here — the records it is
+/// compared against are all expressed in the process's local offset, which
+/// isn't resolved until `exec()` runs, so `exec()` applies that same offset
+/// to this value before comparing.
+fn parse_present(val: &str) -> UResult<time::PrimitiveDateTime> {
+    let full_desc = "[year]-[month]-[day] [hour]:[minute]";
+    let full_format = time::format_description::parse(full_desc).unwrap();
+    if let Ok(pdt) = time::PrimitiveDateTime::parse(val, &full_format) {
+        return Ok(pdt);
+    }
+
+    let time_desc = "[hour]:[minute]";
+    let time_format = time::format_description::parse(time_desc).unwrap();
+    let parsed_time = time::Time::parse(val, &time_format)
+        .map_err(|_| USimpleError::new(1, format!("invalid --present date/time: {val}")))?;
+    let today = OffsetDateTime::now_utc().date();
+    Ok(time::PrimitiveDateTime::new(today, parsed_time))
+}
+
 const RUN_LEVEL_STR: &str = "runlevel";
 const REBOOT_STR: &str = "reboot";
 const SHUTDOWN_STR: &str = "shutdown";
 
 struct Last {
-    last_reboot_ut: Option<Utmpx>,
-    last_shutdown_ut: Option<Utmpx>,
+    // Paired with the `OffsetDateTime` computed for them the one time they
+    // were seen, so later records don't pay for `login_datetime()` again
+    // just to re-derive a timestamp that can't have changed.
+    last_reboot_ut: Option<(Utmpx, OffsetDateTime)>,
+    last_shutdown_ut: Option<(Utmpx, OffsetDateTime)>,
     last_dead_ut: Vec<Utmpx>,
     system: bool,
     file: String,
     time_format: String,
     users: Option<Vec<String>>,
+    offset: time::UtcOffset,
+    byteorder: RecordByteOrder,
+    format: RecordFormat,
+    present: Option<time::PrimitiveDateTime>,
+    present_instant: Option<OffsetDateTime>,
+    limit: Option<usize>,
 }
 
 #[inline]
@@ -105,48 +163,97 @@ fn duration_string(duration: time::Duration) -> String {
 impl Last {
     #[allow(clippy::cognitive_complexity)]
     fn exec(&mut self) -> UResult<()> {
-        let mut ut_stack: Vec<Utmpx> = vec![];
-        Utmpx::iter_all_records_from(&self.file).for_each(|ut| {
-            ut_stack.push(ut) // For 'last' output, older output needs to be printed last (FILO), as UtmpxIter does not implement Rev trait
-                              // A better implementation might include implementing UtmpxIter as doubly linked
-        });
+        self.offset = time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC);
+        self.present_instant = self.present.map(|pdt| pdt.assume_offset(self.offset));
+
+        let mut ut_stack: Vec<Utmpx> =
+            if self.format == RecordFormat::Utmpx && self.byteorder == RecordByteOrder::Native {
+                let mut records = vec![];
+                Utmpx::iter_all_records_from(&self.file).for_each(|ut| {
+                    records.push(ut) // For 'last' output, older output needs to be printed last (FILO), as UtmpxIter does not implement Rev trait
+                                      // A better implementation might include implementing UtmpxIter as doubly linked
+                });
+                records
+            } else {
+                wtmp::parse_records(Path::new(&self.file), self.format, self.byteorder)?
+            };
+
+        let mut printed: usize = 0;
+        if self.limit == Some(0) {
+            return Ok(());
+        }
 
         while let Some(ut) = ut_stack.pop() {
             // println!("|{}| |{}| |{}|", ut.user(), time_string(&ut), ut.tty_device());
-            if ut.is_user_process() {
+            // Each branch that formats or compares this record's own
+            // timestamp resolves it once and passes it down, rather than
+            // each of `time_string`/`end_state_string`/`end_datetime`
+            // calling `login_datetime()` (and so `ut.login_time()`) again.
+            let did_print = if ut.is_user_process() {
+                let dt = self.login_datetime(&ut);
                 let mut dead_proc: Option<Utmpx> = None;
                 if let Some(pos) = self.last_dead_ut.iter().position(|dead_ut| { ut.tty_device() == dead_ut.tty_device() }) {
                     dead_proc = Some(self.last_dead_ut.swap_remove(pos));
                 }
-                self.print_user(&ut, dead_proc.as_ref());
+                self.print_user(&ut, dt, dead_proc.as_ref())
             } else if ut.user() == RUN_LEVEL_STR {
-                self.print_runlevel(&ut);
+                let dt = self.login_datetime(&ut);
+                self.print_runlevel(&ut, dt)
             } else if ut.user() == SHUTDOWN_STR {
-                self.print_shutdown(&ut);
-                self.last_shutdown_ut = Some(ut);
+                let dt = self.login_datetime(&ut);
+                let did_print = self.print_shutdown(&ut, dt);
+                self.last_shutdown_ut = Some((ut, dt));
+                did_print
             } else if ut.user() == REBOOT_STR {
-                self.print_reboot(&ut);
-                self.last_reboot_ut = Some(ut);
+                let dt = self.login_datetime(&ut);
+                let did_print = self.print_reboot(&ut, dt);
+                self.last_reboot_ut = Some((ut, dt));
+                did_print
             } else if ut.user() == "" { // Dead process end date
                 self.last_dead_ut.push(ut);
+                false
+            } else {
+                false
+            };
+
+            if did_print {
+                printed += 1;
+                if let Some(limit) = self.limit {
+                    if printed >= limit {
+                        break;
+                    }
+                }
             }
         }
-        
+
         Ok(())
     }
     
     #[inline]
-    fn time_string(&self, ut: &Utmpx) -> String {
+    fn login_datetime(&self, ut: &Utmpx) -> OffsetDateTime {
+        // `Utmpx` only exposes the record's timestamp through `login_time()`;
+        // re-derive it as a unix timestamp and reapply it against our single
+        // cached offset rather than whatever offset `login_time()` resolved.
+        OffsetDateTime::from_unix_timestamp(ut.login_time().unix_timestamp())
+            .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+            .to_offset(self.offset)
+    }
+
+    #[inline]
+    fn time_string(&self, login_datetime: OffsetDateTime) -> String {
         let description = match self.time_format.as_str() {
-            "short" => {"[month repr:short] [day padding:space] [hour]:[minute]"}
-            _ => {return "".to_string()}
+            "notime" => return "".to_string(),
+            "short" => "[month repr:short] [day padding:space] [hour]:[minute]",
+            "full" => "[weekday repr:short] [month repr:short] [day padding:space] [hour]:[minute]:[second] [year]",
+            "iso" => "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]",
+            _ => unreachable!("value_parser restricts time-format to known values"),
         };
 
         // "%b %e %H:%M"
         let time_format: Vec<time::format_description::FormatItem> =
             time::format_description::parse(description)
                 .unwrap();
-        ut.login_time().format(&time_format).unwrap() // LC_ALL=C
+        login_datetime.format(&time_format).unwrap() // LC_ALL=C
     }
 
     #[inline]
@@ -159,8 +266,11 @@ impl Last {
             Some(val) => { val.to_string() }
             _ => {
                 let description = match self.time_format.as_str() {
-                    "short" => {"[hour]:[minute]"}
-                    _ => {return "".to_string()}
+                    "notime" => return "".to_string(),
+                    "short" => "[hour]:[minute]",
+                    "full" => "[weekday repr:short] [month repr:short] [day padding:space] [hour]:[minute]:[second] [year]",
+                    "iso" => "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]",
+                    _ => unreachable!("value_parser restricts time-format to known values"),
                 };
 
                 // "%H:%M"
@@ -173,68 +283,129 @@ impl Last {
     }
 
     #[inline]
-    fn end_state_string(&self, ut: &Utmpx, dead_ut: Option<&Utmpx>) -> (String, String) {
-        // This function takes a considerable amount of CPU cycles to complete;
-        // root cause seems to be the ut.login_time function, which reads a
-        // file to determine local offset for UTC. Perhaps this function
-        // should be updated to save that UTC offset for subsequent calls
+    fn time_size(&self) -> usize {
+        match self.time_format.as_str() {
+            "notime" => 0,
+            "short" => 3 + 2 + 2 + 1 + 2,
+            "full" => 3 + 1 + 3 + 1 + 2 + 1 + 2 + 1 + 2 + 1 + 2 + 1 + 4,
+            "iso" => 4 + 1 + 2 + 1 + 2 + 1 + 2 + 1 + 2 + 1 + 2 + 1 + 2 + 1 + 2,
+            _ => unreachable!("value_parser restricts time-format to known values"),
+        }
+    }
+
+    /// Width of the end-time column. `full`/`iso` render the end time with
+    /// the same format description as the login time, so they need the same
+    /// width; `short` keeps its existing fixed width (an `[hour]:[minute]`
+    /// or a short status word like "down"/"crash" both fit inside it).
+    #[inline]
+    fn end_time_size(&self) -> usize {
+        match self.time_format.as_str() {
+            "notime" => 0,
+            "short" => 8,
+            _ => self.time_size(),
+        }
+    }
+
+    #[inline]
+    fn end_state_string(&self, curr_datetime: OffsetDateTime, ut: &Utmpx, dead_ut: Option<&Utmpx>) -> (String, String) {
         let mut proc_status: Option<&str> = None;
-        let curr_datetime = ut.login_time();
 
         if let Some(dead) = dead_ut {
-            let dead_datetime = dead.login_time();
+            let dead_datetime = self.login_datetime(dead);
             let time_delta = duration_string(calculate_time_delta(&curr_datetime, &dead_datetime));
             return (self.end_time_string(proc_status, &dead_datetime), time_delta.to_string())
         }
-        
-        let reboot_datetime: Option<OffsetDateTime>;
-        let shutdown_datetime: Option<OffsetDateTime>;
-        if let Some(reboot) = &self.last_reboot_ut {
-            reboot_datetime = Some(reboot.login_time());
-        } else {
-            reboot_datetime = None;
+
+        // Precomputed when the reboot/shutdown record was first seen, so
+        // this doesn't re-derive the same timestamp for every record
+        // printed since.
+        let reboot_datetime = self.last_reboot_ut.as_ref().map(|(_, dt)| *dt);
+        let shutdown_datetime = self.last_shutdown_ut.as_ref().map(|(_, dt)| *dt);
+
+        match self.earliest_bound(reboot_datetime, shutdown_datetime) {
+            None => {
+                if ut.is_user_process() {
+                    (" - still logged in".to_string(), "".to_string())
+                } else {
+                    (" - still running".to_string(), "".to_string())
+                }
+            }
+            Some((end_dt, status)) => {
+                let time_delta = duration_string(calculate_time_delta(&curr_datetime, &end_dt));
+                if ut.is_user_process() { proc_status = Some(status); }
+                (self.end_time_string(proc_status, &end_dt), time_delta.to_string())
+            }
         }
+    }
 
-        if let Some(shutdown) = &self.last_shutdown_ut {
-            shutdown_datetime = Some(shutdown.login_time());
-        } else {
-            shutdown_datetime = None;
+    /// The raw end time of a session, or `None` if it is still ongoing.
+    /// Mirrors the end-state selection in `end_state_string`, but returns
+    /// the `OffsetDateTime` itself for `--present` comparisons instead of a
+    /// formatted display string.
+    #[inline]
+    fn end_datetime(&self, dead_ut: Option<&Utmpx>) -> Option<OffsetDateTime> {
+        if let Some(dead) = dead_ut {
+            return Some(self.login_datetime(dead));
         }
 
-        // let last_datetimes_tuple = (reboot_datetime, shutdown_datetime);
+        let reboot_datetime = self.last_reboot_ut.as_ref().map(|(_, dt)| *dt);
+        let shutdown_datetime = self.last_shutdown_ut.as_ref().map(|(_, dt)| *dt);
 
-        if reboot_datetime.is_none() && shutdown_datetime.is_none() {
-            if ut.is_user_process() {
-                (" - still logged in".to_string(), "".to_string())
-            } else { 
-                (" - still running".to_string(), "".to_string()) 
-            }
-        } else {
-            let reboot = reboot_datetime.unwrap_or_else(|| { time::OffsetDateTime::from_unix_timestamp(0).unwrap() });
-            let shutdown = shutdown_datetime.unwrap_or_else(|| { time::OffsetDateTime::from_unix_timestamp(0).unwrap() });
-            if reboot >= shutdown {
-                let time_delta = duration_string(calculate_time_delta(&curr_datetime, &shutdown));
-                if ut.is_user_process() { proc_status = Some("down"); }
-                (self.end_time_string(proc_status, &shutdown), time_delta.to_string())
-            } else {
-                let time_delta = duration_string(calculate_time_delta(&curr_datetime, &reboot));
-                if ut.is_user_process() { proc_status = Some("crash"); }
-                (self.end_time_string(proc_status, &reboot), time_delta.to_string())
+        self.earliest_bound(reboot_datetime, shutdown_datetime)
+            .map(|(end_dt, _)| end_dt)
+    }
+
+    /// Whichever of a reboot/shutdown bound happened first, with a label
+    /// for which one it was. A missing bound (`None`) means that kind of
+    /// record was never observed — it must not be treated as though it
+    /// happened at the Unix epoch, or a session bounded by only one of the
+    /// two would wrongly appear to have ended decades ago, excluding it
+    /// from `--present` even though it was active at the target instant.
+    #[inline]
+    fn earliest_bound(
+        &self,
+        reboot_datetime: Option<OffsetDateTime>,
+        shutdown_datetime: Option<OffsetDateTime>,
+    ) -> Option<(OffsetDateTime, &'static str)> {
+        match (reboot_datetime, shutdown_datetime) {
+            (None, None) => None,
+            (Some(reboot), None) => Some((reboot, "crash")),
+            (None, Some(shutdown)) => Some((shutdown, "down")),
+            (Some(reboot), Some(shutdown)) => {
+                if reboot >= shutdown {
+                    Some((shutdown, "down"))
+                } else {
+                    Some((reboot, "crash"))
+                }
             }
         }
     }
 
     #[inline]
-    fn print_runlevel(&self, ut: &Utmpx) -> bool {
+    fn matches_filter(&self, ut: &Utmpx) -> bool {
+        match &self.users {
+            None => true,
+            Some(users) => users.iter().any(|val| {
+                let val = val.trim();
+                val == ut.user().trim() || val == ut.tty_device().trim()
+            }),
+        }
+    }
+
+    #[inline]
+    fn print_runlevel(&self, ut: &Utmpx, dt: OffsetDateTime) -> bool {
+        if !self.matches_filter(ut) {
+            return false;
+        }
         if self.system {
             let curr = (ut.pid() % 256) as u8 as char;
             let runlvline = format!("(to lvl {curr})");
-            let (end_date, delta) = self.end_state_string(ut, None);
+            let (end_date, delta) = self.end_state_string(dt, ut, None);
             let host = ut.host();
             self.print_line(
                 RUN_LEVEL_STR,
                 &runlvline,
-                &self.time_string(ut),
+                &self.time_string(dt),
                 &host,
                 &end_date,
                 &delta
@@ -246,19 +417,17 @@ impl Last {
     }
 
     #[inline]
-    fn print_shutdown(&self, ut: &Utmpx) -> bool {
-        if let Some(users) = &self.users {
-            if !users.iter().any(|val| {val.as_str().trim() == "system down" || val.as_str().trim() == ut.user().trim()}) {
-                return false
-            }
+    fn print_shutdown(&self, ut: &Utmpx, dt: OffsetDateTime) -> bool {
+        if !self.matches_filter(ut) {
+            return false;
         }
         let host = ut.host();
         if self.system {
-            let (end_date, delta) = self.end_state_string(ut, None);
+            let (end_date, delta) = self.end_state_string(dt, ut, None);
             self.print_line(
                 SHUTDOWN_STR,
                 "system down",
-                &self.time_string(ut),
+                &self.time_string(dt),
                 &host,
                 &end_date,
                 &delta
@@ -270,13 +439,16 @@ impl Last {
     }
 
     #[inline]
-    fn print_reboot(&self, ut: &Utmpx) -> bool {
-        let (end_date, delta) = self.end_state_string(ut, None);
+    fn print_reboot(&self, ut: &Utmpx, dt: OffsetDateTime) -> bool {
+        if !self.matches_filter(ut) {
+            return false;
+        }
+        let (end_date, delta) = self.end_state_string(dt, ut, None);
         let host = ut.host();
         self.print_line(
             REBOOT_STR,
             "system boot",
-            &self.time_string(ut),
+            &self.time_string(dt),
             &host,
             &end_date,
             &delta
@@ -286,17 +458,27 @@ impl Last {
     }
 
     #[inline]
-    fn print_user(&self, ut: &Utmpx, dead_ut: Option<&Utmpx>) -> bool {
+    fn print_user(&self, ut: &Utmpx, dt: OffsetDateTime, dead_ut: Option<&Utmpx>) -> bool {
+        if !self.matches_filter(ut) {
+            return false;
+        }
+        if let Some(target) = self.present_instant {
+            let end = self.end_datetime(dead_ut);
+            let qualifies = dt <= target && end.map_or(true, |end_time| end_time > target);
+            if !qualifies {
+                return false;
+            }
+        }
         let mut p = PathBuf::from("/dev");
         p.push(ut.tty_device().as_str());
         let host = ut.host();
 
-        let (end_date, delta) = self.end_state_string(ut, dead_ut);
+        let (end_date, delta) = self.end_state_string(dt, ut, dead_ut);
 
         self.print_line(
             ut.user().as_ref(),
             ut.tty_device().as_ref(),
-            self.time_string(ut).as_str(),
+            self.time_string(dt).as_str(),
             &host,
             &end_date,
             &delta
@@ -323,10 +505,12 @@ impl Last {
         write!(buf, " {line:<12}").unwrap();
         write!(buf, " {host_to_print:<16}").unwrap();
 
-        let time_size = 3 + 2 + 2 + 1 + 2;
-
-        write!(buf, " {time:<time_size$}").unwrap();
-        write!(buf, " - {end_time:<8}").unwrap();
+        if self.time_format != "notime" {
+            let time_size = self.time_size();
+            let end_time_size = self.end_time_size();
+            write!(buf, " {time:<time_size$}").unwrap();
+            write!(buf, " - {end_time:<end_time_size$}").unwrap();
+        }
 
         write!(buf, " {delta:^6}").unwrap();
         println!("{}", buf.trim_end());
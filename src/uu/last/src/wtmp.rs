@@ -0,0 +1,222 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Portable, pure-Rust decoding of `wtmp`/`utmpx` records.
+//!
+//! `Utmpx::iter_all_records_from` relies on the host's own `utmpx(5)` struct
+//! layout, so it can only make sense of files produced by a host with the
+//! same record shape and byte order. This module instead decodes records
+//! directly from bytes against an explicit record descriptor, so a `wtmp`
+//! captured on a different architecture (big-endian, 32-bit, an older
+//! pre-utmpx `utmp` layout, ...) can still be read on this one: each record
+//! is normalized into this host's native `utmpx` layout and handed to
+//! `Utmpx::iter_all_records_from` through a scratch file, so the rest of
+//! the pipeline consumes the exact same `Utmpx` it always has.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use uucore::error::{UResult, USimpleError};
+use uucore::utmpx::Utmpx;
+
+/// Which on-disk record shape to decode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// The modern `utmpx(5)` layout (glibc, most Linux distributions).
+    Utmpx,
+    /// The older, smaller `utmp` layout it superseded.
+    Utmp,
+}
+
+impl RecordFormat {
+    pub fn from_str_name(val: &str) -> Option<Self> {
+        match val {
+            "utmpx" => Some(Self::Utmpx),
+            "utmp" => Some(Self::Utmp),
+            _ => None,
+        }
+    }
+}
+
+/// Byte order the source file was written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordByteOrder {
+    /// Assume the file matches this host's own byte order.
+    Native,
+    Little,
+    Big,
+}
+
+impl RecordByteOrder {
+    pub fn from_str_name(val: &str) -> Option<Self> {
+        match val {
+            "native" => Some(Self::Native),
+            "little" => Some(Self::Little),
+            "big" => Some(Self::Big),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RawTimeVal {
+    tv_sec: i32,
+    tv_usec: i32,
+}
+
+// Field layout of the modern `utmpx(5)` record, as laid out by glibc. This
+// is also the layout `Utmpx::iter_all_records_from` expects on disk, so it
+// doubles as the normalized record we re-encode every decoded entry into.
+#[derive(Serialize, Deserialize, Clone)]
+struct RawUtmpxRecord {
+    ut_type: i16,
+    _pad1: i16,
+    ut_pid: i32,
+    ut_line: [u8; 32],
+    ut_id: [u8; 4],
+    ut_user: [u8; 32],
+    ut_host: [u8; 256],
+    ut_exit_termination: i16,
+    ut_exit_exit: i16,
+    ut_session: i32,
+    ut_tv: RawTimeVal,
+    ut_addr_v6: [i32; 4],
+    _unused: [u8; 20],
+}
+
+// Field layout of the classic, pre-utmpx `utmp` record: no session id or
+// IPv6 address, and a shorter hostname field.
+#[derive(Serialize, Deserialize, Clone)]
+struct RawUtmpRecord {
+    ut_type: i16,
+    _pad1: i16,
+    ut_pid: i32,
+    ut_line: [u8; 32],
+    ut_id: [u8; 4],
+    ut_user: [u8; 32],
+    ut_host: [u8; 64],
+    ut_exit_termination: i16,
+    ut_exit_exit: i16,
+    ut_tv: RawTimeVal,
+    _unused: [u8; 20],
+}
+
+impl From<RawUtmpRecord> for RawUtmpxRecord {
+    fn from(raw: RawUtmpRecord) -> Self {
+        let mut ut_host = [0u8; 256];
+        ut_host[..raw.ut_host.len()].copy_from_slice(&raw.ut_host);
+
+        RawUtmpxRecord {
+            ut_type: raw.ut_type,
+            _pad1: 0,
+            ut_pid: raw.ut_pid,
+            ut_line: raw.ut_line,
+            ut_id: raw.ut_id,
+            ut_user: raw.ut_user,
+            ut_host,
+            ut_exit_termination: raw.ut_exit_termination,
+            ut_exit_exit: raw.ut_exit_exit,
+            ut_session: 0,
+            ut_tv: raw.ut_tv,
+            ut_addr_v6: [0; 4],
+            _unused: [0; 20],
+        }
+    }
+}
+
+fn options_for(byteorder: RecordByteOrder) -> impl Options {
+    let opts = bincode::DefaultOptions::new().with_fixint_encoding();
+    match byteorder {
+        RecordByteOrder::Native => {
+            if cfg!(target_endian = "big") {
+                opts.with_big_endian()
+            } else {
+                opts.with_little_endian()
+            }
+        }
+        RecordByteOrder::Little => opts.with_little_endian(),
+        RecordByteOrder::Big => opts.with_big_endian(),
+    }
+}
+
+fn native_options() -> impl Options {
+    options_for(RecordByteOrder::Native)
+}
+
+static SCRATCH_FILE_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Decode every record in `path` according to the given `format` and
+/// `byteorder`, yielding the same `Utmpx`-shaped records `exec()` already
+/// consumes. Rather than constructing `Utmpx` values directly (its inner
+/// libc record is private to `uucore`), each decoded record is re-encoded
+/// into this host's native `utmpx` layout and written to a scratch file,
+/// which `Utmpx::iter_all_records_from` then parses as it would any other
+/// `wtmp` file.
+pub fn parse_records(
+    path: &Path,
+    format: RecordFormat,
+    byteorder: RecordByteOrder,
+) -> UResult<Vec<Utmpx>> {
+    let bytes = fs::read(path).map_err(|e| USimpleError::new(1, format!("{}: {e}", path.display())))?;
+
+    let record_size = match format {
+        RecordFormat::Utmpx => std::mem::size_of::<RawUtmpxRecord>(),
+        RecordFormat::Utmp => std::mem::size_of::<RawUtmpRecord>(),
+    };
+
+    if record_size == 0 || bytes.len() % record_size != 0 {
+        return Err(USimpleError::new(
+            1,
+            format!(
+                "{}: file size {} is not a multiple of the {record_size}-byte record size for this format",
+                path.display(),
+                bytes.len()
+            ),
+        ));
+    }
+
+    let decode_opts = options_for(byteorder);
+    let encode_opts = native_options();
+
+    let mut native_bytes = Vec::with_capacity(bytes.len());
+    for chunk in bytes.chunks_exact(record_size) {
+        let normalized: RawUtmpxRecord = match format {
+            RecordFormat::Utmpx => decode_opts.deserialize(chunk).map_err(|e| {
+                USimpleError::new(1, format!("{}: truncated or malformed record: {e}", path.display()))
+            })?,
+            RecordFormat::Utmp => {
+                let raw: RawUtmpRecord = decode_opts.deserialize(chunk).map_err(|e| {
+                    USimpleError::new(1, format!("{}: truncated or malformed record: {e}", path.display()))
+                })?;
+                raw.into()
+            }
+        };
+
+        encode_opts
+            .serialize_into(&mut native_bytes, &normalized)
+            .map_err(|e| USimpleError::new(1, format!("{}: failed to re-encode record: {e}", path.display())))?;
+    }
+
+    let scratch_path = scratch_file_path();
+    fs::File::create(&scratch_path)
+        .and_then(|mut f| f.write_all(&native_bytes))
+        .map_err(|e| USimpleError::new(1, format!("{}: {e}", scratch_path.display())))?;
+
+    let records: Vec<Utmpx> = Utmpx::iter_all_records_from(&scratch_path).collect();
+    let _ = fs::remove_file(&scratch_path);
+
+    Ok(records)
+}
+
+fn scratch_file_path() -> std::path::PathBuf {
+    let n = SCRATCH_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("uu-last-wtmp-{}-{n}.tmp", std::process::id()))
+}